@@ -5,15 +5,46 @@ use crate::input_processing::types::Input;
 use anyhow::{anyhow, Result};
 use aptos_crypto::poseidon_bn254;
 use aptos_keyless_common::input_processing::config::CircuitConfig;
-use aptos_types::keyless::{Configuration, IdCommitment};
+use aptos_types::{jwks::rsa::RSA_JWK, keyless::IdCommitment};
 use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use std::{fmt, str::FromStr};
+
+// `Fr` output of compute_public_inputs_hash/compute_idc_hash, guaranteed to round-trip through Display/FromStr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicInputsHash(pub Fr);
+
+impl PublicInputsHash {
+    // Canonical fixed-width (0x + 64 hex chars) big-endian encoding, for clients that prefer hex over decimal.
+    pub fn to_fixed_hex(&self) -> String {
+        let bytes = self.0.into_bigint().to_bytes_be();
+        format!("0x{}", hex::encode(bytes))
+    }
+}
+
+impl fmt::Display for PublicInputsHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for PublicInputsHash {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Fr::from_str(s)
+            .map(PublicInputsHash)
+            .map_err(|_| anyhow!("Failed to parse '{s}' as a PublicInputsHash (decimal Fr string)"))
+    }
+}
 
 pub fn compute_idc_hash(
     input: &Input,
     config: &CircuitConfig,
     pepper_fr: Fr,
     jwt_payload: &str,
-) -> Result<Fr> {
+) -> Result<PublicInputsHash> {
     let uid_field = FieldParser::find_and_parse_field(jwt_payload, &input.uid_key)?;
 
     let mut frs: Vec<Fr> = Vec::new();
@@ -44,131 +75,248 @@ pub fn compute_idc_hash(
     )?;
     frs.push(uid_key_hash_fr);
 
-    poseidon_bn254::hash_scalars(frs)
+    poseidon_bn254::hash_scalars(frs).map(PublicInputsHash)
 }
 
+// Default max RSA modulus size in bytes when a config doesn't set `rsa_modulus_bytes` (2048-bit keys).
+// Raising `rsa_modulus_bytes` only changes what's rejected below; `to_poseidon_scalar()` is circuit-side
+// and unverified for other sizes here, so don't raise this in production until the circuit supports it.
 pub const RSA_MODULUS_BYTES: usize = 256;
 
-pub fn compute_temp_pubkey_frs(input: &Input) -> Result<([Fr; 3], Fr)> {
+// Decoded length, in bytes, of an `RSA_JWK`'s modulus (the base64url-encoded `n` param).
+fn rsa_modulus_len_bytes(jwk: &RSA_JWK) -> Result<usize> {
+    Ok(URL_SAFE_NO_PAD
+        .decode(&jwk.n)
+        .map_err(|e| anyhow!("Failed to base64-decode RSA JWK modulus 'n': {e}"))?
+        .len())
+}
+
+// Rejects JWKs whose modulus exceeds the circuit's configured `rsa_modulus_bytes` bound (or
+// RSA_MODULUS_BYTES by default) instead of silently producing an incorrect pubkey_hash_fr.
+fn check_rsa_modulus_len(jwk: &RSA_JWK, config: &CircuitConfig) -> Result<()> {
+    let max_rsa_modulus_bytes = config
+        .max_lengths
+        .get("rsa_modulus_bytes")
+        .copied()
+        .unwrap_or(RSA_MODULUS_BYTES);
+    let modulus_len_bytes = rsa_modulus_len_bytes(jwk)?;
+    if modulus_len_bytes > max_rsa_modulus_bytes {
+        return Err(anyhow!(
+            "RSA JWK modulus is {} bytes, which exceeds the circuit's configured maximum of {} bytes",
+            modulus_len_bytes,
+            max_rsa_modulus_bytes
+        ));
+    }
+    Ok(())
+}
+
+// Packs input.epk's serialized bytes into scalars. to_bytes() handles the variant-specific
+// serialization, so this works for any EphemeralPublicKey variant (Ed25519, secp256r1/WebAuthn, ...).
+pub fn compute_temp_pubkey_frs(input: &Input, config: &CircuitConfig) -> Result<([Fr; 3], Fr)> {
+    let epk_bytes = input.epk.to_bytes();
+    let max_commited_epk_bytes = *config
+        .max_lengths
+        .get("max_commited_epk_bytes")
+        .ok_or_else(|| anyhow!("Can't find key max_commited_epk_bytes in config"))?;
+
     let temp_pubkey_frs_with_len = poseidon_bn254::keyless::pad_and_pack_bytes_to_scalars_with_len(
-        input.epk.to_bytes().as_slice(),
-        Configuration::new_for_devnet().max_commited_epk_bytes as usize, // TODO should put this in my local config
-    )?;
+        epk_bytes.as_slice(),
+        max_commited_epk_bytes,
+    )
+    .map_err(|e| {
+        anyhow!(
+            "Ephemeral public key ({} bytes) doesn't fit in the configured max_commited_epk_bytes ({}): {e}",
+            epk_bytes.len(),
+            max_commited_epk_bytes
+        )
+    })?;
+
+    // The circuit commits to exactly 3 31-byte-chunk scalars plus 1 length scalar,
+    // which only holds when max_commited_epk_bytes packs into exactly 3 chunks
+    // (i.e. is in (62, 93]). Any other configured budget would panic on an
+    // out-of-bounds index, or silently drop EPK data, if we didn't check here.
+    if temp_pubkey_frs_with_len.len() != 4 {
+        return Err(anyhow!(
+            "max_commited_epk_bytes of {} packs the EPK into {} scalars, but this circuit layout \
+             requires exactly 4 (3 EPK chunks + 1 length); max_commited_epk_bytes must be in (62, 93]",
+            max_commited_epk_bytes,
+            temp_pubkey_frs_with_len.len()
+        ));
+    }
+
+    let (epk_frs, len_fr) = temp_pubkey_frs_with_len.split_at(3);
+    let epk_frs: [Fr; 3] = epk_frs
+        .try_into()
+        .map_err(|_| anyhow!("Expected exactly 3 EPK scalars, got {}", epk_frs.len()))?;
+
+    Ok((epk_frs, len_fr[0]))
+}
+
+// A single labeled scalar that went into the public-inputs hash preimage. string_hash_input is
+// set for entries hashed via pad_and_hash_string: the cleartext and the max_length it was padded to.
+#[derive(Debug, Clone)]
+pub struct PublicInputsHashEntry {
+    pub label: &'static str,
+    pub fr: Fr,
+    pub string_hash_input: Option<(String, usize)>,
+}
+
+// Every labeled scalar pushed into frs by public_inputs_hash_breakdown, plus the resulting hash.
+#[derive(Debug, Clone)]
+pub struct PublicInputsBreakdown {
+    pub entries: Vec<PublicInputsHashEntry>,
+    pub hash: PublicInputsHash,
+}
 
-    Ok((
-        temp_pubkey_frs_with_len[..3]
-            .try_into()
-            .expect("Length here should always be 3"),
-        temp_pubkey_frs_with_len[3],
-    ))
+fn fr_entry(label: &'static str, fr: Fr) -> PublicInputsHashEntry {
+    PublicInputsHashEntry {
+        label,
+        fr,
+        string_hash_input: None,
+    }
+}
+
+fn string_hash_entry(
+    label: &'static str,
+    fr: Fr,
+    cleartext: &str,
+    max_length: usize,
+) -> PublicInputsHashEntry {
+    PublicInputsHashEntry {
+        label,
+        fr,
+        string_hash_input: Some((cleartext.to_string(), max_length)),
+    }
 }
 
-pub fn compute_public_inputs_hash(input: &Input, config: &CircuitConfig) -> anyhow::Result<Fr> {
+pub fn compute_public_inputs_hash(
+    input: &Input,
+    config: &CircuitConfig,
+) -> anyhow::Result<PublicInputsHash> {
+    Ok(public_inputs_hash_breakdown(input, config)?.hash)
+}
+
+// Same as compute_public_inputs_hash, but also returns every labeled scalar pushed into the
+// preimage, so a mismatch against the on-chain keyless authenticator can be localized to a field.
+pub fn public_inputs_hash_breakdown(
+    input: &Input,
+    config: &CircuitConfig,
+) -> anyhow::Result<PublicInputsBreakdown> {
     let pepper_fr = input.pepper_fr;
     let jwt_parts = &input.jwt_parts;
     let jwk = &input.jwk;
     let iss_field = FieldParser::find_and_parse_field(&jwt_parts.payload_decoded()?, "iss")?;
-    let (temp_pubkey_frs, temp_pubkey_len) = compute_temp_pubkey_frs(input)?;
+    let (temp_pubkey_frs, temp_pubkey_len) = compute_temp_pubkey_frs(input, config)?;
 
     let extra_field = field_check_input::parsed_extra_field_or_default(input)?;
 
-    let override_aud_val_hashed = poseidon_bn254::pad_and_hash_string(
-        &field_check_input::override_aud_value(input)?,
-        IdCommitment::MAX_AUD_VAL_BYTES,
-    )?;
+    let override_aud_val = field_check_input::override_aud_value(input)?;
+    let override_aud_val_hashed =
+        poseidon_bn254::pad_and_hash_string(&override_aud_val, IdCommitment::MAX_AUD_VAL_BYTES)?;
     let use_override_aud = if let Some(_override_aud_val) = &input.idc_aud {
         ark_bn254::Fr::from(1)
     } else {
         ark_bn254::Fr::from(0)
     };
 
-    // Add the epk as padded and packed scalars
-    let mut frs = Vec::from(temp_pubkey_frs);
+    let mut entries = Vec::with_capacity(14);
 
-    frs.push(temp_pubkey_len);
+    // Add the epk as padded and packed scalars
+    for (i, epk_fr) in temp_pubkey_frs.iter().enumerate() {
+        entries.push(fr_entry(
+            match i {
+                0 => "epk[0]",
+                1 => "epk[1]",
+                _ => "epk[2]",
+            },
+            *epk_fr,
+        ));
+    }
+    entries.push(fr_entry("epk_len", temp_pubkey_len));
 
     // Add the id_commitment as a scalar
-    let addr_idc_fr = compute_idc_hash(input, config, pepper_fr, &jwt_parts.payload_decoded()?)?;
-    frs.push(addr_idc_fr);
+    let addr_idc_fr = compute_idc_hash(input, config, pepper_fr, &jwt_parts.payload_decoded()?)?.0;
+    entries.push(fr_entry("addr_idc", addr_idc_fr));
 
     // Add the exp_timestamp_secs as a scalar
-    frs.push(Fr::from(input.exp_date_secs));
+    entries.push(fr_entry(
+        "exp_timestamp_secs",
+        Fr::from(input.exp_date_secs),
+    ));
 
     // Add the epk lifespan as a scalar
-    frs.push(Fr::from(input.exp_horizon_secs));
-
-    let iss_val_hash = poseidon_bn254::pad_and_hash_string(
+    entries.push(fr_entry("exp_horizon_secs", Fr::from(input.exp_horizon_secs)));
+
+    let iss_max_length = *config
+        .max_lengths
+        .get("iss_value")
+        .ok_or_else(|| anyhow!("Can't find key iss in config"))?;
+    let iss_val_hash = poseidon_bn254::pad_and_hash_string(&iss_field.value, iss_max_length)?;
+    entries.push(string_hash_entry(
+        "iss_val_hash",
+        iss_val_hash,
         &iss_field.value,
-        *config
-            .max_lengths
-            .get("iss_value")
-            .ok_or_else(|| anyhow!("Can't find key iss in config"))?,
-    )?;
-    frs.push(iss_val_hash);
+        iss_max_length,
+    ));
 
     let use_extra_field_fr = Fr::from(input.use_extra_field() as u64);
-    let extra_field_hash = poseidon_bn254::pad_and_hash_string(
+    entries.push(fr_entry("use_extra_field", use_extra_field_fr));
+
+    let extra_field_max_length = *config
+        .max_lengths
+        .get("extra_field")
+        .ok_or_else(|| anyhow!("Can't find key extra in config"))?;
+    let extra_field_hash =
+        poseidon_bn254::pad_and_hash_string(&extra_field.whole_field, extra_field_max_length)?;
+    entries.push(string_hash_entry(
+        "extra_field_hash",
+        extra_field_hash,
         &extra_field.whole_field,
-        *config
-            .max_lengths
-            .get("extra_field")
-            .ok_or_else(|| anyhow!("Can't find key extra in config"))?,
-    )?;
-    frs.push(use_extra_field_fr);
-    frs.push(extra_field_hash);
+        extra_field_max_length,
+    ));
 
     // Add the hash of the jwt_header with the "." separator appended
     let jwt_header_str = jwt_parts.header_undecoded_with_dot();
-    let jwt_header_hash = poseidon_bn254::pad_and_hash_string(
+    let jwt_header_max_length = config.max_lengths["jwt_header_with_separator"];
+    let jwt_header_hash =
+        poseidon_bn254::pad_and_hash_string(&jwt_header_str, jwt_header_max_length)?;
+    entries.push(string_hash_entry(
+        "jwt_header_hash",
+        jwt_header_hash,
         &jwt_header_str,
-        config.max_lengths["jwt_header_with_separator"],
-    )?;
-    frs.push(jwt_header_hash);
+        jwt_header_max_length,
+    ));
 
+    check_rsa_modulus_len(jwk, config)?;
     let pubkey_hash_fr = jwk.to_poseidon_scalar()?;
-    frs.push(pubkey_hash_fr);
-
-    frs.push(override_aud_val_hashed);
-
-    frs.push(use_override_aud);
-
-    let result = poseidon_bn254::hash_scalars(frs)?;
-
-    // debugging print statements which we used to check consistency with authenticator
-    //     println!("Num EPK scalars:    {}", 4);
-    //        for (i, e) in temp_pubkey_frs.iter().enumerate() {
-    //            println!("EPK Fr[{}]:               {}", i, e.to_string())
-    //        }
-    //        println!("EPK Fr[{}]:                   {}", 4, temp_pubkey_len);
-    //        println!("IDC:                          {}", addr_idc_fr);
-    //        println!("exp_timestamp_secs:           {}", Fr::from(input.exp_date));
-    //        println!("exp_horizon_secs:             {}", Fr::from(input.exp_horizon));
-    //println!("iss val:              \'{}\'", &iss_field.value);
-    //println!("iss val hash:               {}", iss_val_hash);
-    //println!("max iss val length: {}", config.field_check_inputs.max_value_length("iss").unwrap());
-
-    //    println!("addr_seed:              {}", &addr_idc_fr);
-    //    println!("Extra field val:              {}", &extra_field.whole_field);
-    //    println!("Use extra field:              {}", use_extra_field_fr);
-    //    println!("Extra field hash:             {}", extra_field_hash);
-    //    println!("JWT header val:               {}", jwt_header_str);
-    //    println!("JWT header hash:              {}", jwt_header_hash);
-    //    println!("JWK hash:                     {}", pubkey_hash_fr);
-    //    println!("Override aud hash:            {}", override_aud_val_hashed);
-    //    println!("Use override aud:             {}", use_override_aud);
-    //    println!("result (public_inputs_hash):  {}", result.to_string());
-
-    Ok(result)
+    entries.push(fr_entry("pubkey_hash", pubkey_hash_fr));
+
+    entries.push(string_hash_entry(
+        "override_aud_hash",
+        override_aud_val_hashed,
+        &override_aud_val,
+        IdCommitment::MAX_AUD_VAL_BYTES,
+    ));
+
+    entries.push(fr_entry("use_override_aud", use_override_aud));
+
+    let frs: Vec<Fr> = entries.iter().map(|entry| entry.fr).collect();
+    let hash = PublicInputsHash(poseidon_bn254::hash_scalars(frs)?);
+
+    Ok(PublicInputsBreakdown { entries, hash })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::compute_public_inputs_hash;
+    use super::{compute_public_inputs_hash, compute_temp_pubkey_frs, PublicInputsHash};
     use crate::input_processing::types::Input;
     use aptos_crypto::{
         ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
         encoding_type::EncodingType,
         poseidon_bn254,
+        secp256r1_ecdsa::{
+            PrivateKey as Secp256r1EcdsaPrivateKey, PublicKey as Secp256r1EcdsaPublicKey,
+        },
     };
     use aptos_keyless_common::input_processing::{
         config::CircuitConfig,
@@ -248,6 +396,137 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_public_inputs_hash_breakdown_matches_compute_public_inputs_hash() {
+        let michael_pk_mod_str: &'static str =      "6S7asUuzq5Q_3U9rbs-PkDVIdjgmtgWreG5qWPsC9xXZKiMV1AiV9LXyqQsAYpCqEDM3XbfmZqGb48yLhb_XqZaKgSYaC_h2DjM7lgrIQAp9902Rr8fUmLN2ivr5tnLxUUOnMOc2SQtr9dgzTONYW5Zu3PwyvAWk5D6ueIUhLtYzpcB-etoNdL3Ir2746KIy_VUsDwAM7dhrqSK8U2xFCGlau4ikOTtvzDownAMHMrfE7q1B6WZQDAQlBmxRQsyKln5DIsKv6xauNsHRgBAKctUxZG8M4QJIx3S6Aughd3RZC4Ca5Ae9fd8L8mlNYBCrQhOZ7dS0f4at4arlLcajtw";
+        let michael_pk_kid_str: &'static str = "test-rsa";
+        let jwk = RSA_JWK::new_256_aqab(michael_pk_kid_str, michael_pk_mod_str);
+
+        let jwt_b64 = "eyJhbGciOiJSUzI1NiIsImtpZCI6InRlc3RfandrIiwidHlwIjoiSldUIn0.eyJpc3MiOiJodHRwczovL2FjY291bnRzLmdvb2dsZS5jb20iLCJhenAiOiI0MDc0MDg3MTgxOTIuYXBwcy5nb29nbGV1c2VyY29udGVudC5jb20iLCJhdWQiOiI0MDc0MDg3MTgxOTIuYXBwcy5nb29nbGV1c2VyY29udGVudC5jb20iLCJzdWIiOiIxMTM5OTAzMDcwODI4OTk3MTg3NzUiLCJoZCI6ImFwdG9zbGFicy5jb20iLCJlbWFpbCI6Im1pY2hhZWxAYXB0b3NsYWJzLmNvbSIsImVtYWlsX3ZlcmlmaWVkIjp0cnVlLCJhdF9oYXNoIjoiYnhJRVN1STU5SW9aYjVhbENBU3FCZyIsIm5hbWUiOiJNaWNoYWVsIFN0cmFrYSIsInBpY3R1cmUiOiJodHRwczovL2xoMy5nb29nbGV1c2VyY29udGVudC5jb20vYS9BQ2c4b2NKdlk0a1ZVQlJ0THhlMUlxS1dMNWk3dEJESnpGcDlZdVdWWE16d1BwYnM9czk2LWMiLCJnaXZlbl9uYW1lIjoiTWljaGFlbCIsImZhbWlseV9uYW1lIjoiU3RyYWthIiwibG9jYWxlIjoiZW4iLCJpYXQiOjE3MDAyNTU5NDQsImV4cCI6MjcwMDI1OTU0NCwibm9uY2UiOiI5Mzc5OTY2MjUyMjQ4MzE1NTY1NTA5NzkwNjEzNDM5OTAyMDA1MTU4ODcxODE1NzA4ODczNjMyNDMxNjk4MTkzNDIxNzk1MDMzNDk4In0.Ejdu3RLnqe0qyS4qJrT7z58HwQISbHoqG1bNcM2JvQDF9h-SAm4X9R6oGfD_wSD8dvs9vaLbZCUhOB8pL-bmXXF25ZkDk1-PU1lWDnuZ77cYQKOrT259LdfPtscdn2DBClfQ5Faepzq-OdPZcfbNegpdclZyIn_jT_EJgO8BTRLP5QHpcPe5f9EsgP7ISw2UNIEB6mDn0hqVnB6MvAPmmYEY6VGgwqwKs1ntih8TEnL3bfJ3511MwhYJvnpAQ1l-c_htAGaVm98tC-rWD5QQKGAf1ONXG3_Rfq6JsTdBBq_p_3zxNUbD2WiEOSBRptZDNcGCbtI2SuPCY5o00NE6aQ";
+
+        let ephemeral_private_key: Ed25519PrivateKey = EncodingType::Hex
+            .decode_key(
+                "zkid test ephemeral private key",
+                "0x76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7"
+                    .as_bytes()
+                    .to_vec(),
+            )
+            .unwrap();
+        let ephemeral_public_key_unwrapped: Ed25519PublicKey =
+            Ed25519PublicKey::from(&ephemeral_private_key);
+        let epk = EphemeralPublicKey::ed25519(ephemeral_public_key_unwrapped);
+
+        let input = Input {
+            jwt_parts: JwtParts::from_b64(jwt_b64).unwrap(),
+            jwk: Arc::new(jwk),
+            epk,
+            epk_blinder_fr: Fr::from_str("42").unwrap(),
+            exp_date_secs: 1900255944,
+            exp_horizon_secs: 100255944,
+            pepper_fr: Fr::from_str("76").unwrap(),
+            uid_key: String::from("sub"),
+            extra_field: Some(String::from("family_name")),
+            idc_aud: None,
+            skip_aud_checks: false,
+        };
+
+        let config: CircuitConfig = serde_yaml::from_str(
+            &fs::read_to_string("conversion_config.yml").expect("Unable to read file"),
+        )
+        .expect("should parse correctly");
+
+        let breakdown = super::public_inputs_hash_breakdown(&input, &config).unwrap();
+        let hash = compute_public_inputs_hash(&input, &config).unwrap();
+
+        assert_eq!(breakdown.hash, hash);
+        assert_eq!(breakdown.entries.len(), 14);
+
+        let labels: Vec<&str> = breakdown.entries.iter().map(|e| e.label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "epk[0]",
+                "epk[1]",
+                "epk[2]",
+                "epk_len",
+                "addr_idc",
+                "exp_timestamp_secs",
+                "exp_horizon_secs",
+                "iss_val_hash",
+                "use_extra_field",
+                "extra_field_hash",
+                "jwt_header_hash",
+                "pubkey_hash",
+                "override_aud_hash",
+                "use_override_aud",
+            ]
+        );
+
+        let iss_entry = breakdown
+            .entries
+            .iter()
+            .find(|e| e.label == "iss_val_hash")
+            .unwrap();
+        let (cleartext, max_length) = iss_entry.string_hash_input.as_ref().unwrap();
+        assert_eq!(cleartext, "https://accounts.google.com");
+        assert_eq!(
+            iss_entry.fr,
+            poseidon_bn254::pad_and_hash_string(cleartext, *max_length).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rsa_modulus_len_bytes() {
+        let mod_str: &'static str = "6S7asUuzq5Q_3U9rbs-PkDVIdjgmtgWreG5qWPsC9xXZKiMV1AiV9LXyqQsAYpCqEDM3XbfmZqGb48yLhb_XqZaKgSYaC_h2DjM7lgrIQAp9902Rr8fUmLN2ivr5tnLxUUOnMOc2SQtr9dgzTONYW5Zu3PwyvAWk5D6ueIUhLtYzpcB-etoNdL3Ir2746KIy_VUsDwAM7dhrqSK8U2xFCGlau4ikOTtvzDownAMHMrfE7q1B6WZQDAQlBmxRQsyKln5DIsKv6xauNsHRgBAKctUxZG8M4QJIx3S6Aughd3RZC4Ca5Ae9fd8L8mlNYBCrQhOZ7dS0f4at4arlLcajtw";
+        let jwk = RSA_JWK::new_256_aqab("test-rsa", mod_str);
+
+        // This is a 2048-bit key, so its modulus should decode to 256 bytes.
+        assert_eq!(super::rsa_modulus_len_bytes(&jwk).unwrap(), 256);
+    }
+
+    #[test]
+    fn test_check_rsa_modulus_len_rejects_oversized_key() {
+        let mod_str: &'static str = "6S7asUuzq5Q_3U9rbs-PkDVIdjgmtgWreG5qWPsC9xXZKiMV1AiV9LXyqQsAYpCqEDM3XbfmZqGb48yLhb_XqZaKgSYaC_h2DjM7lgrIQAp9902Rr8fUmLN2ivr5tnLxUUOnMOc2SQtr9dgzTONYW5Zu3PwyvAWk5D6ueIUhLtYzpcB-etoNdL3Ir2746KIy_VUsDwAM7dhrqSK8U2xFCGlau4ikOTtvzDownAMHMrfE7q1B6WZQDAQlBmxRQsyKln5DIsKv6xauNsHRgBAKctUxZG8M4QJIx3S6Aughd3RZC4Ca5Ae9fd8L8mlNYBCrQhOZ7dS0f4at4arlLcajtw";
+        let jwk = RSA_JWK::new_256_aqab("test-rsa", mod_str);
+
+        let mut config: CircuitConfig = serde_yaml::from_str(
+            &fs::read_to_string("conversion_config.yml").expect("Unable to read file"),
+        )
+        .expect("should parse correctly");
+        // Simulate a circuit only willing to commit to a 1024-bit (128-byte) modulus.
+        config.max_lengths.insert("rsa_modulus_bytes".to_string(), 128);
+
+        let err = super::check_rsa_modulus_len(&jwk, &config).unwrap_err();
+        assert!(err.to_string().contains("exceeds the circuit's configured maximum"));
+    }
+
+    #[test]
+    fn test_check_rsa_modulus_len_uses_actual_length_not_default() {
+        // A synthetic 3072-bit-sized (384-byte) modulus: 512 base64url characters,
+        // decoding cleanly (no padding) to exactly 384 bytes.
+        let large_mod_str = "A".repeat(512);
+        let jwk = RSA_JWK::new_256_aqab("test-rsa-3072", &large_mod_str);
+        assert_eq!(super::rsa_modulus_len_bytes(&jwk).unwrap(), 384);
+
+        let mut config: CircuitConfig = serde_yaml::from_str(
+            &fs::read_to_string("conversion_config.yml").expect("Unable to read file"),
+        )
+        .expect("should parse correctly");
+
+        // Without an explicit bound, the 2048-bit default correctly rejects this key.
+        assert!(super::check_rsa_modulus_len(&jwk, &config).is_err());
+
+        // A circuit explicitly configured for 3072-bit keys accepts it.
+        //
+        // NOTE: this only proves `check_rsa_modulus_len` reads the real decoded
+        // modulus length and respects a raised `rsa_modulus_bytes` bound. It does
+        // NOT prove `RSA_JWK::to_poseidon_scalar()` produces a correct hash for a
+        // modulus this size — that's a circuit-side guarantee outside this
+        // module's control. See the note on [`super::RSA_MODULUS_BYTES`].
+        config.max_lengths.insert("rsa_modulus_bytes".to_string(), 384);
+        assert!(super::check_rsa_modulus_len(&jwk, &config).is_ok());
+    }
+
     #[test]
     fn test_hashing_cognito_bad() {
         let michael_pk_mod_str: &'static str =      "6S7asUuzq5Q_3U9rbs-PkDVIdjgmtgWreG5qWPsC9xXZKiMV1AiV9LXyqQsAYpCqEDM3XbfmZqGb48yLhb_XqZaKgSYaC_h2DjM7lgrIQAp9902Rr8fUmLN2ivr5tnLxUUOnMOc2SQtr9dgzTONYW5Zu3PwyvAWk5D6ueIUhLtYzpcB-etoNdL3Ir2746KIy_VUsDwAM7dhrqSK8U2xFCGlau4ikOTtvzDownAMHMrfE7q1B6WZQDAQlBmxRQsyKln5DIsKv6xauNsHRgBAKctUxZG8M4QJIx3S6Aughd3RZC4Ca5Ae9fd8L8mlNYBCrQhOZ7dS0f4at4arlLcajtw";
@@ -370,4 +649,177 @@ mod tests {
 
         let hash = compute_public_inputs_hash(&input, &config).unwrap();
     }
+
+    fn epk_round_trip_test_config() -> CircuitConfig {
+        let mut config: CircuitConfig = serde_yaml::from_str(
+            &fs::read_to_string("conversion_config.yml").expect("Unable to read file"),
+        )
+        .expect("should parse correctly");
+        config.max_lengths.insert(
+            "max_commited_epk_bytes".to_string(),
+            Configuration::new_for_testing().max_commited_epk_bytes as usize,
+        );
+        config
+    }
+
+    fn assert_temp_pubkey_frs_round_trip(epk: EphemeralPublicKey) {
+        let config = epk_round_trip_test_config();
+        let max_commited_epk_bytes = config.max_lengths["max_commited_epk_bytes"];
+
+        let epk_bytes = epk.to_bytes();
+        let input = Input {
+            jwt_parts: JwtParts::from_b64(
+                "eyJhbGciOiJSUzI1NiJ9.eyJpc3MiOiJ0ZXN0In0.sig",
+            )
+            .unwrap(),
+            jwk: Arc::new(RSA_JWK::new_256_aqab("test-rsa", "AQAB")),
+            epk,
+            epk_blinder_fr: Fr::from_str("42").unwrap(),
+            exp_date_secs: 1900255944,
+            exp_horizon_secs: 100255944,
+            pepper_fr: Fr::from_str("76").unwrap(),
+            uid_key: String::from("sub"),
+            extra_field: None,
+            idc_aud: None,
+            skip_aud_checks: false,
+        };
+
+        let (frs, len) = compute_temp_pubkey_frs(&input, &config).unwrap();
+
+        let expected = poseidon_bn254::keyless::pad_and_pack_bytes_to_scalars_with_len(
+            epk_bytes.as_slice(),
+            max_commited_epk_bytes,
+        )
+        .unwrap();
+
+        assert_eq!(Vec::from(frs), expected[..3].to_vec());
+        assert_eq!(len, expected[3]);
+    }
+
+    #[test]
+    fn test_compute_temp_pubkey_frs_round_trip_ed25519() {
+        let ephemeral_private_key: Ed25519PrivateKey = EncodingType::Hex
+            .decode_key(
+                "zkid test ephemeral private key",
+                "0x76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7"
+                    .as_bytes()
+                    .to_vec(),
+            )
+            .unwrap();
+        let epk = EphemeralPublicKey::ed25519(Ed25519PublicKey::from(&ephemeral_private_key));
+
+        assert_temp_pubkey_frs_round_trip(epk);
+    }
+
+    #[test]
+    fn test_compute_temp_pubkey_frs_round_trip_secp256r1_ecdsa() {
+        let ephemeral_private_key = Secp256r1EcdsaPrivateKey::generate_for_testing();
+        let epk = EphemeralPublicKey::secp256r1_ecdsa(Secp256r1EcdsaPublicKey::from(
+            &ephemeral_private_key,
+        ));
+
+        assert_temp_pubkey_frs_round_trip(epk);
+    }
+
+    #[test]
+    fn test_compute_temp_pubkey_frs_rejects_epk_over_budget() {
+        let ephemeral_private_key: Ed25519PrivateKey = EncodingType::Hex
+            .decode_key(
+                "zkid test ephemeral private key",
+                "0x76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7"
+                    .as_bytes()
+                    .to_vec(),
+            )
+            .unwrap();
+        let epk = EphemeralPublicKey::ed25519(Ed25519PublicKey::from(&ephemeral_private_key));
+
+        let mut config = epk_round_trip_test_config();
+        // An impossibly small budget should be rejected instead of panicking.
+        config
+            .max_lengths
+            .insert("max_commited_epk_bytes".to_string(), 1);
+
+        let input = Input {
+            jwt_parts: JwtParts::from_b64("eyJhbGciOiJSUzI1NiJ9.eyJpc3MiOiJ0ZXN0In0.sig").unwrap(),
+            jwk: Arc::new(RSA_JWK::new_256_aqab("test-rsa", "AQAB")),
+            epk,
+            epk_blinder_fr: Fr::from_str("42").unwrap(),
+            exp_date_secs: 1900255944,
+            exp_horizon_secs: 100255944,
+            pepper_fr: Fr::from_str("76").unwrap(),
+            uid_key: String::from("sub"),
+            extra_field: None,
+            idc_aud: None,
+            skip_aud_checks: false,
+        };
+
+        assert!(compute_temp_pubkey_frs(&input, &config).is_err());
+    }
+
+    #[test]
+    fn test_compute_temp_pubkey_frs_rejects_budget_outside_3_chunk_layout() {
+        let ephemeral_private_key: Ed25519PrivateKey = EncodingType::Hex
+            .decode_key(
+                "zkid test ephemeral private key",
+                "0x76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7"
+                    .as_bytes()
+                    .to_vec(),
+            )
+            .unwrap();
+        let epk = EphemeralPublicKey::ed25519(Ed25519PublicKey::from(&ephemeral_private_key));
+
+        let mut config = epk_round_trip_test_config();
+        // Large enough to fit the EPK's bytes, but small enough to pack into only
+        // 2 31-byte chunks + 1 length scalar (3 total) instead of the circuit's
+        // required 4 — previously this indexed out of bounds instead of erroring.
+        config
+            .max_lengths
+            .insert("max_commited_epk_bytes".to_string(), 40);
+
+        let input = Input {
+            jwt_parts: JwtParts::from_b64("eyJhbGciOiJSUzI1NiJ9.eyJpc3MiOiJ0ZXN0In0.sig").unwrap(),
+            jwk: Arc::new(RSA_JWK::new_256_aqab("test-rsa", "AQAB")),
+            epk,
+            epk_blinder_fr: Fr::from_str("42").unwrap(),
+            exp_date_secs: 1900255944,
+            exp_horizon_secs: 100255944,
+            pepper_fr: Fr::from_str("76").unwrap(),
+            uid_key: String::from("sub"),
+            extra_field: None,
+            idc_aud: None,
+            skip_aud_checks: false,
+        };
+
+        let err = compute_temp_pubkey_frs(&input, &config).unwrap_err();
+        assert!(err.to_string().contains("must be in (62, 93]"));
+    }
+
+    #[test]
+    fn test_public_inputs_hash_round_trip() {
+        use ark_std::{test_rng, UniformRand};
+
+        let mut rng = test_rng();
+        let mut scalars: Vec<Fr> = (0..100).map(|_| Fr::rand(&mut rng)).collect();
+        // The reduction boundary: 0, 1, and p - 1 (the largest representable scalar).
+        scalars.extend([Fr::from(0u64), Fr::from(1u64), -Fr::from(1u64)]);
+
+        for fr in scalars {
+            let wrapped = PublicInputsHash(fr);
+            let parsed = PublicInputsHash::from_str(&wrapped.to_string()).unwrap();
+            assert_eq!(parsed, wrapped);
+        }
+    }
+
+    #[test]
+    fn test_public_inputs_hash_to_fixed_hex_is_fixed_width() {
+        assert_eq!(
+            PublicInputsHash(Fr::from(0u64)).to_fixed_hex(),
+            format!("0x{}", "0".repeat(64))
+        );
+        // "0x" + 64 hex chars, regardless of how small the scalar is.
+        assert_eq!(
+            PublicInputsHash(Fr::from(1u64)).to_fixed_hex().len(),
+            66
+        );
+    }
 }